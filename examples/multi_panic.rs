@@ -0,0 +1,14 @@
+//! Panics once on a worker thread and once on main, to exercise the "note printed only once"
+//! contract used by `tests/multi_panic.rs`.
+
+fn main() {
+	reproducible_panic::install();
+
+	let worker = std::thread::Builder::new()
+		.name("worker".into())
+		.spawn(|| panic!("first panic"))
+		.expect("spawn worker thread");
+	let _ = worker.join();
+
+	panic!("second panic");
+}
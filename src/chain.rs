@@ -0,0 +1,24 @@
+//! Chaining to a previously installed panic hook.
+
+use std::panic::PanicHookInfo;
+use std::sync::OnceLock;
+
+type PreviousHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send>;
+
+static PREVIOUS_HOOK: OnceLock<PreviousHook> = OnceLock::new();
+
+/// Take the currently installed panic hook and remember it, so [`panic_hook()`][crate::panic_hook]
+/// can forward to it after printing its own output.
+pub(crate) fn capture_previous_hook() {
+	let previous = std::panic::take_hook();
+	// Ignore the error: if a previous hook was already captured, keep that one so repeated calls
+	// to `install_chained()` don't chain through this crate's own hook.
+	let _ = PREVIOUS_HOOK.set(previous);
+}
+
+/// Forward `info` to the previously captured panic hook, if any.
+pub(crate) fn call_previous_hook(info: &PanicHookInfo<'_>) {
+	if let Some(previous) = PREVIOUS_HOOK.get() {
+		previous(info);
+	}
+}
@@ -0,0 +1,119 @@
+//! Builder-style configuration for which fields [`panic_hook()`][crate::panic_hook] prints.
+
+use std::sync::{OnceLock, RwLock};
+
+const DEFAULT_NOTE: &str = "note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace";
+
+/// Configures which fields [`panic_hook()`][crate::panic_hook] prints, and how.
+///
+/// Build one with [`Config::new()`], then install it with [`Config::install()`].
+///
+/// ```
+/// reproducible_panic::Config::new()
+///     .thread_name(false)
+///     .note(false)
+///     .install();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+	thread_name: bool,
+	thread_name_replacement: Option<String>,
+	leading_blank_line: bool,
+	note: Option<String>,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			thread_name: true,
+			thread_name_replacement: None,
+			leading_blank_line: true,
+			note: Some(DEFAULT_NOTE.to_string()),
+		}
+	}
+}
+
+impl Config {
+	/// Create a config with the same defaults as [`panic_hook()`][crate::panic_hook].
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Whether to print the `thread '<name>'` prefix at all. Enabled by default.
+	pub fn thread_name(mut self, enabled: bool) -> Self {
+		self.thread_name = enabled;
+		self
+	}
+
+	/// Print `replacement` instead of the real thread name.
+	///
+	/// Has no effect if [`Config::thread_name()`] is disabled.
+	pub fn thread_name_replacement(mut self, replacement: impl Into<String>) -> Self {
+		self.thread_name_replacement = Some(replacement.into());
+		self
+	}
+
+	/// Whether to print a blank line before the `thread '<name>' panicked at ...` line. Enabled by default.
+	pub fn leading_blank_line(mut self, enabled: bool) -> Self {
+		self.leading_blank_line = enabled;
+		self
+	}
+
+	/// Whether to print the "note: run with `RUST_BACKTRACE=1`..." note. Enabled by default.
+	pub fn note(mut self, enabled: bool) -> Self {
+		self.note = enabled.then(|| DEFAULT_NOTE.to_string());
+		self
+	}
+
+	/// Print `text` instead of the default "note: run with `RUST_BACKTRACE=1`..." note.
+	pub fn note_text(mut self, text: impl Into<String>) -> Self {
+		self.note = Some(text.into());
+		self
+	}
+
+	pub(crate) fn thread_name_enabled(&self) -> bool {
+		self.thread_name
+	}
+
+	pub(crate) fn resolved_thread_name<'a>(&'a self, actual: &'a str) -> &'a str {
+		self.thread_name_replacement.as_deref().unwrap_or(actual)
+	}
+
+	pub(crate) fn leading_blank_line_enabled(&self) -> bool {
+		self.leading_blank_line
+	}
+
+	pub(crate) fn note_text_or_none(&self) -> Option<&str> {
+		self.note.as_deref()
+	}
+
+	/// Install this config and [`panic_hook()`][crate::panic_hook] as the global panic hook.
+	pub fn install(self) {
+		set_config(self);
+		std::panic::set_hook(Box::new(crate::panic_hook));
+	}
+
+	/// Install this config and [`panic_hook()`][crate::panic_hook] as the global panic hook,
+	/// chained to whichever hook was previously installed. See [`crate::install_chained()`].
+	pub fn install_chained(self) {
+		set_config(self);
+		crate::install_chained();
+	}
+}
+
+static CONFIG: OnceLock<RwLock<Config>> = OnceLock::new();
+
+/// Install a [`Config`] for [`panic_hook()`][crate::panic_hook] to use, without touching the
+/// installed panic hook itself.
+pub fn set_config(config: Config) {
+	let lock = CONFIG.get_or_init(|| RwLock::new(Config::default()));
+	*lock.write().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+/// Get the currently installed [`Config`], or the default one if none was installed.
+pub(crate) fn get_config() -> Config {
+	match CONFIG.get() {
+		Some(lock) => lock.read().unwrap_or_else(|e| e.into_inner()).clone(),
+		None => Config::default(),
+	}
+}
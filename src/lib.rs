@@ -29,68 +29,143 @@
 //! ```
 //!
 //! Note the "12993" in the output. This number will be different every time you run the program, ruining your snapshot tests.
+//!
+//! By default, whether a backtrace is printed (and how) still depends on the `RUST_BACKTRACE`
+//! environment variable, which is itself not reproducible across machines and CI runners. Call
+//! [`set_backtrace_style()`] to force a fixed [`BacktraceStyle`] instead.
+//!
+//! Even with a fixed style, a real backtrace still contains frame addresses and absolute paths
+//! that differ between machines. Install a [`SanitizeConfig`] with [`set_sanitize_config()`] to
+//! normalize those away, yielding a stable, diffable backtrace.
+//!
+//! To assert on the hook's output directly instead of scraping a subprocess's stderr, redirect it
+//! with [`set_output()`].
+//!
+//! Use [`Config`] to control which fields are printed at all, for example to get output that is
+//! just `location` + message.
+//!
+//! [`install()`] replaces whichever panic hook was previously installed. Use [`install_chained()`]
+//! instead to forward to it afterwards, so this crate composes with other hooks.
 
 #![allow(clippy::needless_doctest_main, reason = "included to show intended use in a full program")]
 
+mod chain;
+mod config;
+mod output;
+mod sanitize;
+mod style;
+
+pub use config::{Config, set_config};
+pub use output::{OutputGuard, set_output};
+pub use sanitize::{SanitizeConfig, get_sanitize_config, set_sanitize_config};
+pub use style::{BacktraceStyle, get_backtrace_style, set_backtrace_style};
+
 use std::panic::PanicHookInfo;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::io::Write;
 
 /// Install [`panic_hook()`] as the global panic hook.
 pub fn install() {
 	std::panic::set_hook(Box::new(panic_hook));
 }
 
+/// Install [`panic_hook()`] as the global panic hook, chained to whichever hook was previously
+/// installed.
+///
+/// The previous hook (for example one installed by a logging or telemetry crate) is invoked after
+/// [`panic_hook()`] has printed its own output, instead of being discarded.
+pub fn install_chained() {
+	chain::capture_previous_hook();
+	std::panic::set_hook(Box::new(panic_hook));
+}
+
 /// A panic hook that doesn't print any non-reproducible information by default.
 ///
 /// The hook tries to mimic the default hook, except that it does not print non-reproducible information like the ID of the panicking thread by default.
 ///
 /// However, if you set `RUST_BACKTRACE=full`, the printed backtrace will almost certainly include non-reproducible output.
 pub fn panic_hook(info: &PanicHookInfo<'_>) {
-	let backtrace = std::backtrace::Backtrace::capture();
+	// A style of `Short`/`Full` is a promise of a deterministic backtrace regardless of the
+	// environment, so it must force a capture instead of deferring to `RUST_BACKTRACE`/
+	// `RUST_LIB_BACKTRACE` -- otherwise the backtrace stays `Disabled` on a clean environment
+	// (such as CI) and the style has no effect at all.
+	let backtrace = match get_backtrace_style() {
+		Some(BacktraceStyle::Short) | Some(BacktraceStyle::Full) => std::backtrace::Backtrace::force_capture(),
+		Some(BacktraceStyle::Off) | None => std::backtrace::Backtrace::capture(),
+	};
 	let location = info.location();
 	let msg = info.payload_as_str();
 	let current_thread = std::thread::current();
 	let thread_name = current_thread.name().unwrap_or("<unnamed>");
-	let mut stderr = std::io::stderr().lock();
 
+	let config = config::get_config();
+
+	output::with_output(|out| {
+		if config.leading_blank_line_enabled() {
+			writeln!(out).ok();
+		}
+		let thread_prefix = config.thread_name_enabled().then(|| {
+			format!("thread '{}' ", config.resolved_thread_name(thread_name))
+		});
+		let thread_prefix = thread_prefix.as_deref().unwrap_or("");
+		if let Some(location) = location {
+			writeln!(out, "{thread_prefix}panicked at {location}").ok();
+		} else {
+			writeln!(out, "{thread_prefix}panicked").ok();
+		}
+		if let Some(msg) = msg {
+			writeln!(out, "{msg}").ok();
+		}
 
-	if let Some(location) = location {
-		writeln!(stderr, "\nthread '{thread_name}' panicked at {location}").ok();
-	} else {
-		writeln!(stderr, "\nthread '{thread_name}' panicked").ok();
-	}
-	if let Some(msg) = msg {
-		writeln!(stderr, "{msg}").ok();
-	}
+		// Shared across all threads and both panics of a mixed captured/uncaptured sequence: the
+		// "run with `RUST_BACKTRACE=1`" note is only ever printed from the `Disabled` arm below
+		// (a captured backtrace never needs it), so scoping this flag to that arm already gives
+		// the documented "printed only once" behavior regardless of what earlier panics captured.
+		static FIRST_PANIC: AtomicBool = AtomicBool::new(true);
 
-	static FIRST_PANIC: AtomicBool = AtomicBool::new(true);
+		// `RUST_LIB_BACKTRACE` takes priority over `RUST_BACKTRACE` when std decides whether to
+		// capture a backtrace, so `RUST_BACKTRACE=0` alone doesn't guarantee `Backtrace::capture()`
+		// returns `Disabled`. Without an explicit style override, treat `RUST_BACKTRACE=0` exactly
+		// like unset ourselves, on top of whatever std already decided.
+		let status = if get_backtrace_style().is_none() && style::env_explicitly_disables_backtrace() {
+			std::backtrace::BacktraceStatus::Disabled
+		} else {
+			backtrace.status()
+		};
 
-	match backtrace.status() {
-		std::backtrace::BacktraceStatus::Captured => {
-			if std::env::var_os("RUST_BACKTRACE").is_some_and(|x| x == "full") {
-				writeln!(&mut stderr, "stack backtrace:\n{backtrace:#}").ok();
-			} else {
-				writeln!(&mut stderr, "stack backtrace:\n{backtrace}").ok();
+		match status {
+			std::backtrace::BacktraceStatus::Captured => {
+				let style = get_backtrace_style().unwrap_or_else(style::style_from_env);
+				let rendered = match style {
+					BacktraceStyle::Off => None,
+					BacktraceStyle::Short => Some(format!("{backtrace}")),
+					BacktraceStyle::Full => Some(format!("{backtrace:#}")),
+				};
+				if let Some(rendered) = rendered {
+					let rendered = match get_sanitize_config() {
+						Some(config) => sanitize::sanitize(&rendered, &config),
+						None => rendered,
+					};
+					writeln!(out, "stack backtrace:\n{rendered}").ok();
+				}
 			}
-		}
-		std::backtrace::BacktraceStatus::Disabled => {
-			if FIRST_PANIC.swap(false, Ordering::Relaxed) {
-				writeln!(
-					&mut stderr,
-					"note: run with `RUST_BACKTRACE=1` environment variable to display a \
-					backtrace"
-				).ok();
+			std::backtrace::BacktraceStatus::Disabled
+				if get_backtrace_style() != Some(BacktraceStyle::Off) && FIRST_PANIC.swap(false, Ordering::Relaxed) =>
+			{
+				if let Some(note) = config.note_text_or_none() {
+					writeln!(out, "{note}").ok();
+				}
 				if cfg!(miri) {
 					writeln!(
-						&mut stderr,
+						out,
 						"note: in Miri, you may have to set `MIRIFLAGS=-Zmiri-env-forward=RUST_BACKTRACE` \
 						for the environment variable to have an effect"
 					).ok();
 				}
 			}
+			std::backtrace::BacktraceStatus::Unsupported => (),
+			_ => (),
 		}
-		std::backtrace::BacktraceStatus::Unsupported => (),
-		_ => (),
-	}
+	});
+
+	chain::call_previous_hook(info);
 }
@@ -0,0 +1,81 @@
+//! Pluggable output sink for [`panic_hook()`][crate::panic_hook], so tests can capture its
+//! output without scraping a subprocess's real stderr.
+
+use std::cell::Cell;
+use std::io::Write;
+use std::marker::PhantomData;
+
+thread_local! {
+	static OUTPUT_OVERRIDE: Cell<Option<*mut dyn Write>> = const { Cell::new(None) };
+}
+
+/// Redirect [`panic_hook()`][crate::panic_hook]'s output on the current thread to `writer`,
+/// until the returned [`OutputGuard`] is dropped.
+///
+/// While installed, the hook writes to `writer` instead of locking [`std::io::stderr()`]. This
+/// lets tests assert on the exact bytes the hook would print, without spawning a subprocess. Since
+/// `writer` is only borrowed, the caller keeps ownership and can inspect it once the guard is
+/// dropped.
+///
+/// ```
+/// use std::panic::catch_unwind;
+///
+/// let mut buffer = Vec::new();
+/// {
+///     let _guard = reproducible_panic::set_output(&mut buffer);
+///     std::panic::set_hook(Box::new(reproducible_panic::panic_hook));
+///     let _ = catch_unwind(|| panic!("oh no"));
+/// }
+///
+/// let output = String::from_utf8(buffer).unwrap();
+/// assert!(output.contains("oh no"));
+/// ```
+pub fn set_output<'a, W: Write + 'a>(writer: &'a mut W) -> OutputGuard<'a> {
+	let writer: &'a mut (dyn Write + 'a) = writer;
+	let ptr: *mut (dyn Write + 'a) = writer;
+	// SAFETY: this only erases the lifetime `'a` from the pointer's type, it does not extend how
+	// long the pointee is actually valid for. The `OutputGuard<'a>` we return borrows from
+	// `writer` for `'a`, so the erased pointer is restored (by `OutputGuard::drop`) before `'a`
+	// ends and `with_output()` can therefore never dereference it past that point.
+	let ptr: *mut dyn Write = unsafe { std::mem::transmute(ptr) };
+	let previous = OUTPUT_OVERRIDE.with(|cell| cell.replace(Some(ptr)));
+	OutputGuard {
+		previous,
+		marker: PhantomData,
+	}
+}
+
+/// Restores the previous output override (if any) when dropped.
+///
+/// Returned by [`set_output()`]. Borrows the writer passed to [`set_output()`] for its lifetime,
+/// so the writer can't be read until the guard is dropped.
+#[must_use = "the output override is restored when this guard is dropped"]
+pub struct OutputGuard<'a> {
+	previous: Option<*mut dyn Write>,
+	marker: PhantomData<&'a mut ()>,
+}
+
+impl Drop for OutputGuard<'_> {
+	fn drop(&mut self) {
+		OUTPUT_OVERRIDE.with(|cell| cell.set(self.previous));
+	}
+}
+
+/// Run `f` with the current thread's output override, or with locked [`std::io::stderr()`] if
+/// none is installed.
+pub(crate) fn with_output(f: impl FnOnce(&mut dyn Write)) {
+	let ptr = OUTPUT_OVERRIDE.with(Cell::get);
+	match ptr {
+		Some(ptr) => {
+			// Safety: `ptr` was installed by a live `OutputGuard` (it is cleared by
+			// `OutputGuard::drop` before the borrow it was created from can end), so it still
+			// points at a valid, exclusively borrowed `dyn Write` for the duration of this call.
+			let writer = unsafe { &mut *ptr };
+			f(writer);
+		},
+		None => {
+			let mut stderr = std::io::stderr().lock();
+			f(&mut stderr);
+		},
+	}
+}
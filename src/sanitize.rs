@@ -0,0 +1,255 @@
+//! Sanitizing of backtraces so that they are stable and diffable across machines.
+
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+
+/// Configuration for sanitizing a rendered backtrace so that it no longer contains
+/// machine-specific output.
+///
+/// Install a config with [`set_sanitize_config()`] to have [`panic_hook()`][crate::panic_hook]
+/// sanitize backtraces before printing them. This is opt-in: without a config installed, the
+/// backtrace is printed exactly as rendered by [`std::backtrace::Backtrace`].
+#[derive(Debug, Clone)]
+pub struct SanitizeConfig {
+	normalize_addresses: bool,
+	strip_prefixes: Vec<PathBuf>,
+	collapse_at_lines: bool,
+}
+
+impl Default for SanitizeConfig {
+	/// Create a config that normalizes addresses and strips the paths most likely to differ
+	/// between machines: the crate's `CARGO_MANIFEST_DIR`, the cargo registry and the rustup
+	/// sysroot.
+	fn default() -> Self {
+		// `CARGO_MANIFEST_DIR` is a build-time cargo variable: it's not normally set in the
+		// environment of the binary once it runs, so it must be captured at compile time with
+		// `env!()` rather than read at runtime with `std::env::var_os()`.
+		let mut strip_prefixes = vec![PathBuf::from(env!("CARGO_MANIFEST_DIR"))];
+		if let Some(cargo_home) = cargo_home() {
+			strip_prefixes.push(cargo_home.join("registry"));
+		}
+		if let Some(rustup_home) = rustup_home() {
+			strip_prefixes.push(rustup_home.join("toolchains"));
+		}
+		Self {
+			normalize_addresses: true,
+			strip_prefixes,
+			collapse_at_lines: false,
+		}
+	}
+}
+
+/// The cargo home directory: `CARGO_HOME` if set, otherwise the default `~/.cargo`.
+fn cargo_home() -> Option<PathBuf> {
+	std::env::var_os("CARGO_HOME").map(PathBuf::from).or_else(|| Some(home_dir()?.join(".cargo")))
+}
+
+/// The rustup home directory: `RUSTUP_HOME` if set, otherwise the default `~/.rustup`.
+fn rustup_home() -> Option<PathBuf> {
+	std::env::var_os("RUSTUP_HOME").map(PathBuf::from).or_else(|| Some(home_dir()?.join(".rustup")))
+}
+
+/// The current user's home directory, without pulling in a dependency just for this.
+fn home_dir() -> Option<PathBuf> {
+	std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).map(PathBuf::from)
+}
+
+impl SanitizeConfig {
+	/// Create a config with the default prefixes. See [`SanitizeConfig::default()`].
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Replace every hex frame address (`0x...`) with the fixed placeholder `0x<addr>`.
+	///
+	/// Enabled by default.
+	pub fn normalize_addresses(mut self, enabled: bool) -> Self {
+		self.normalize_addresses = enabled;
+		self
+	}
+
+	/// Add a path prefix to strip from absolute source paths, turning them into file-relative
+	/// paths like `src/foo.rs`.
+	pub fn strip_prefix(mut self, prefix: impl Into<PathBuf>) -> Self {
+		self.strip_prefixes.push(prefix.into());
+		self
+	}
+
+	/// Re-indent `at <path>:<line>:<col>` continuation lines to a fixed width.
+	///
+	/// The path itself is left as is: only [`SanitizeConfig::strip_prefix()`] and the default
+	/// prefixes turn it into a file-relative path, and a path under none of them stays absolute.
+	///
+	/// Disabled by default.
+	pub fn collapse_at_lines(mut self, enabled: bool) -> Self {
+		self.collapse_at_lines = enabled;
+		self
+	}
+
+	/// Install this config as the global sanitize config for [`panic_hook()`][crate::panic_hook].
+	pub fn install(self) {
+		set_sanitize_config(self);
+	}
+}
+
+static SANITIZE_CONFIG: OnceLock<RwLock<Option<SanitizeConfig>>> = OnceLock::new();
+
+/// Install a [`SanitizeConfig`] for [`panic_hook()`][crate::panic_hook] to use when rendering
+/// backtraces.
+pub fn set_sanitize_config(config: SanitizeConfig) {
+	let lock = SANITIZE_CONFIG.get_or_init(|| RwLock::new(None));
+	*lock.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+}
+
+/// Get the currently installed [`SanitizeConfig`], if any.
+pub fn get_sanitize_config() -> Option<SanitizeConfig> {
+	SANITIZE_CONFIG.get()?.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Sanitize a rendered backtrace according to `config`.
+pub(crate) fn sanitize(text: &str, config: &SanitizeConfig) -> String {
+	let mut out = String::with_capacity(text.len());
+	for line in text.split_inclusive('\n') {
+		let mut line = if config.normalize_addresses {
+			normalize_addresses(line)
+		} else {
+			line.to_string()
+		};
+		if !config.strip_prefixes.is_empty() {
+			line = strip_path_prefixes(&line, &config.strip_prefixes);
+		}
+		if config.collapse_at_lines {
+			line = collapse_at_line(&line);
+		}
+		out.push_str(&line);
+	}
+	out
+}
+
+/// Replace every `0x<hex digits>` substring with the fixed placeholder `0x<addr>`.
+fn normalize_addresses(line: &str) -> String {
+	let mut out = String::with_capacity(line.len());
+	let mut rest = line;
+	while let Some(pos) = rest.find("0x") {
+		out.push_str(&rest[..pos]);
+		let after = &rest[pos + 2..];
+		let hex_len = after.bytes().take_while(u8::is_ascii_hexdigit).count();
+		if hex_len > 0 {
+			out.push_str("0x<addr>");
+			rest = &after[hex_len..];
+		} else {
+			out.push_str("0x");
+			rest = after;
+		}
+	}
+	out.push_str(rest);
+	out
+}
+
+/// Strip the first matching prefix from `line`, turning an absolute path embedded in it into a
+/// file-relative one.
+fn strip_path_prefixes(line: &str, prefixes: &[PathBuf]) -> String {
+	for prefix in prefixes {
+		let prefix = prefix.to_string_lossy();
+		if let Some(pos) = line.find(prefix.as_ref()) {
+			let before = &line[..pos];
+			let after = &line[pos + prefix.len()..];
+			let after = after.trim_start_matches(['/', '\\']);
+			return format!("{before}{after}");
+		}
+	}
+	line.to_string()
+}
+
+/// Re-indent a `at <path>:<line>:<col>` continuation line to a fixed indentation, leaving the path unchanged.
+fn collapse_at_line(line: &str) -> String {
+	let trimmed = line.trim_start();
+	match trimmed.strip_prefix("at ") {
+		Some(rest) => format!("             at {rest}"),
+		None => line.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn normalize_addresses_replaces_hex_frame_addresses() {
+		assert_eq!(
+			normalize_addresses("   3: std::rt::lang_start::{{closure}}::h1234 (0x55d2a1b4c9f0)\n"),
+			"   3: std::rt::lang_start::{{closure}}::h1234 (0x<addr>)\n",
+		);
+		assert_eq!(normalize_addresses("no addresses here\n"), "no addresses here\n");
+		assert_eq!(normalize_addresses("0x is not an address\n"), "0x is not an address\n");
+	}
+
+	#[test]
+	fn strip_path_prefixes_makes_paths_file_relative() {
+		let prefixes = vec![PathBuf::from("/home/user/project")];
+		assert_eq!(
+			strip_path_prefixes("             at /home/user/project/src/foo.rs:10:5\n", &prefixes),
+			"             at src/foo.rs:10:5\n",
+		);
+		assert_eq!(
+			strip_path_prefixes("             at /somewhere/else/src/foo.rs:10:5\n", &prefixes),
+			"             at /somewhere/else/src/foo.rs:10:5\n",
+		);
+	}
+
+	#[test]
+	fn collapse_at_line_normalizes_indentation() {
+		assert_eq!(
+			collapse_at_line("     at src/foo.rs:10:5\n"),
+			"             at src/foo.rs:10:5\n",
+		);
+		assert_eq!(collapse_at_line("   3: some::function\n"), "   3: some::function\n");
+	}
+
+	#[test]
+	fn cargo_home_and_rustup_home_fall_back_to_the_user_home_directory() {
+		let saved_cargo_home = std::env::var_os("CARGO_HOME");
+		let saved_rustup_home = std::env::var_os("RUSTUP_HOME");
+		let saved_home = std::env::var_os("HOME");
+
+		unsafe {
+			std::env::remove_var("CARGO_HOME");
+			std::env::remove_var("RUSTUP_HOME");
+			std::env::set_var("HOME", "/home/example");
+		}
+		assert_eq!(cargo_home(), Some(PathBuf::from("/home/example/.cargo")));
+		assert_eq!(rustup_home(), Some(PathBuf::from("/home/example/.rustup")));
+
+		unsafe {
+			std::env::set_var("CARGO_HOME", "/custom/cargo");
+			std::env::set_var("RUSTUP_HOME", "/custom/rustup");
+		}
+		assert_eq!(cargo_home(), Some(PathBuf::from("/custom/cargo")));
+		assert_eq!(rustup_home(), Some(PathBuf::from("/custom/rustup")));
+
+		unsafe {
+			restore_var("CARGO_HOME", saved_cargo_home);
+			restore_var("RUSTUP_HOME", saved_rustup_home);
+			restore_var("HOME", saved_home);
+		}
+	}
+
+	unsafe fn restore_var(name: &str, value: Option<std::ffi::OsString>) {
+		match value {
+			Some(value) => unsafe { std::env::set_var(name, value) },
+			None => unsafe { std::env::remove_var(name) },
+		}
+	}
+
+	#[test]
+	fn sanitize_applies_all_enabled_passes() {
+		let config = SanitizeConfig {
+			normalize_addresses: true,
+			strip_prefixes: vec![PathBuf::from("/home/user/project")],
+			collapse_at_lines: true,
+		};
+		let input = "   3: some::function (0x55d2a1b4c9f0)\n     at /home/user/project/src/foo.rs:10:5\n";
+		let expected = "   3: some::function (0x<addr>)\n             at src/foo.rs:10:5\n";
+		assert_eq!(sanitize(input, &config), expected);
+	}
+}
@@ -0,0 +1,75 @@
+//! Runtime-configurable backtrace rendering style.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Controls whether and how [`panic_hook()`][crate::panic_hook] captures and renders a backtrace.
+///
+/// This is independent of the `RUST_BACKTRACE` environment variable, which is not reproducible
+/// across machines and CI runners. Set a style with [`set_backtrace_style()`] to make the hook's
+/// output deterministic regardless of the environment it runs in: [`BacktraceStyle::Short`] and
+/// [`BacktraceStyle::Full`] force a backtrace to be captured even if `RUST_BACKTRACE` and
+/// `RUST_LIB_BACKTRACE` are both unset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BacktraceStyle {
+	/// Never print a backtrace, even if one was captured, and suppress the "run with `RUST_BACKTRACE=1`" note.
+	Off,
+	/// Force a backtrace to be captured, and print it using its normal `{backtrace}` rendering.
+	Short,
+	/// Force a backtrace to be captured, and print it using its verbose `{backtrace:#}` rendering.
+	Full,
+}
+
+/// `0` means unset, otherwise the discriminant of [`BacktraceStyle`] plus one.
+static BACKTRACE_STYLE: AtomicU8 = AtomicU8::new(0);
+
+/// Set the [`BacktraceStyle`] used by [`panic_hook()`][crate::panic_hook].
+///
+/// This overrides the `RUST_BACKTRACE` environment variable entirely, giving callers (such as
+/// snapshot tests) a way to force a fixed backtrace mode regardless of the runner's environment.
+pub fn set_backtrace_style(style: BacktraceStyle) {
+	let value = match style {
+		BacktraceStyle::Off => 1,
+		BacktraceStyle::Short => 2,
+		BacktraceStyle::Full => 3,
+	};
+	BACKTRACE_STYLE.store(value, Ordering::Relaxed);
+}
+
+/// Get the [`BacktraceStyle`] previously set with [`set_backtrace_style()`].
+///
+/// Returns [`None`] if no style was set, in which case [`panic_hook()`][crate::panic_hook] falls
+/// back to the `RUST_BACKTRACE` environment variable.
+pub fn get_backtrace_style() -> Option<BacktraceStyle> {
+	match BACKTRACE_STYLE.load(Ordering::Relaxed) {
+		1 => Some(BacktraceStyle::Off),
+		2 => Some(BacktraceStyle::Short),
+		3 => Some(BacktraceStyle::Full),
+		_ => None,
+	}
+}
+
+/// The [`BacktraceStyle`] implied by the `RUST_BACKTRACE` environment variable, used when no
+/// style was set with [`set_backtrace_style()`].
+///
+/// Only an exact (case-insensitive) match of `full` selects [`BacktraceStyle::Full`]. Every other
+/// value, including `0` and unset, falls back to [`BacktraceStyle::Short`] -- whether anything is
+/// printed at all still depends on whether a backtrace was actually captured, which std decides
+/// based on the same environment variable.
+pub(crate) fn style_from_env() -> BacktraceStyle {
+	match std::env::var("RUST_BACKTRACE") {
+		Ok(value) if value.eq_ignore_ascii_case("full") => BacktraceStyle::Full,
+		_ => BacktraceStyle::Short,
+	}
+}
+
+/// Whether `RUST_BACKTRACE` explicitly asks for backtraces to be disabled (`0`).
+///
+/// Modern `rustc`/std treat `RUST_BACKTRACE=0` exactly like the variable being unset: no
+/// backtrace is captured, and the "run with `RUST_BACKTRACE=1`" note is printed. `std::backtrace`
+/// already implements that by itself when it decides whether to capture a backtrace, *except*
+/// when `RUST_LIB_BACKTRACE` is also set, which takes priority over `RUST_BACKTRACE` and can force
+/// capturing even while `RUST_BACKTRACE=0`. [`panic_hook()`][crate::panic_hook] calls this to
+/// treat `0` as unset on top of that, regardless of `RUST_LIB_BACKTRACE`.
+pub(crate) fn env_explicitly_disables_backtrace() -> bool {
+	std::env::var_os("RUST_BACKTRACE").is_some_and(|value| value == "0")
+}
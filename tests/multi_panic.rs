@@ -0,0 +1,28 @@
+//! Verifies the "note printed only once" contract across multiple panics: a panic on a worker
+//! thread followed by a panic on main must only print the `RUST_BACKTRACE=1` note for the first
+//! one. This needs a real subprocess, since the test harness runs each test on its own worker
+//! thread rather than on the process's actual main thread.
+
+use std::process::Command;
+
+#[test]
+fn note_is_printed_only_once_across_panics() {
+	let output = Command::new(env!("CARGO"))
+		.args(["run", "--quiet", "--example", "multi_panic"])
+		.env_remove("RUST_BACKTRACE")
+		.output()
+		.expect("run multi_panic example");
+
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	let note = "note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace";
+
+	assert_eq!(stderr.matches(note).count(), 1, "note should be printed exactly once, got:\n{stderr}");
+	assert!(stderr.contains("thread 'worker' panicked"), "missing worker panic, got:\n{stderr}");
+	assert!(stderr.contains("thread 'main' panicked"), "missing main panic, got:\n{stderr}");
+
+	let worker_panic_pos = stderr.find("thread 'worker' panicked").unwrap();
+	let note_pos = stderr.find(note).unwrap();
+	let main_panic_pos = stderr.find("thread 'main' panicked").unwrap();
+	assert!(worker_panic_pos < note_pos, "note should follow the first (worker) panic, got:\n{stderr}");
+	assert!(note_pos < main_panic_pos, "note should not be repeated after the second (main) panic, got:\n{stderr}");
+}